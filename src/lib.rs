@@ -2,19 +2,42 @@
 //!
 //! This crate provides functionalities to subscribe and publish messages
 //! to different topics.
+//!
+//! Delivery is *at-least-once*: failed callbacks are retried with exponential
+//! backoff, a message whose subscriber crashes mid-callback is redelivered because
+//! its claim is rolled back with the subscriber's transaction, and rows left
+//! committed in `processing` are reset by the reaper (see [`PubSub::start_reaper`]).
+//! Callbacks should therefore be idempotent.
 
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use sqlx::postgres::PgListener;
 use sqlx::PgPool;
 use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
 use thiserror::Error;
-use tokio_stream::StreamExt;
+use tokio_stream::{Stream, StreamExt};
 use tokio_util::sync::CancellationToken;
 
+/// Default number of retries after the first delivery attempt before a message is marked `failed`.
+const DEFAULT_MAX_RETRIES: i32 = 5;
+/// Default base delay for the exponential backoff between retries.
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Default cap for the exponential backoff between retries.
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(3600);
+
 /// A Pub/Sub struct for message interactions.
 #[derive(Clone)]
 pub struct PubSub {
     pool: PgPool,
     shutdown: CancellationToken,
+    max_retries: i32,
+    base_delay: Duration,
+    max_delay: Duration,
 }
 
 impl PubSub {
@@ -27,9 +50,43 @@ impl PubSub {
         Ok(Self {
             pool,
             shutdown: CancellationToken::new(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
         })
     }
 
+    /// Set the maximum number of retries before a message is marked `failed`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_retries` - The number of retries to allow after the first attempt.
+    pub fn max_retries(mut self, max_retries: i32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the base delay used for the exponential backoff between retries.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_delay` - The base of the retry delay `base_delay * 2^attempts`; the
+    ///   first retry, after one failed attempt, waits twice this.
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Set the cap that the exponential backoff between retries grows to.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_delay` - The largest delay a retry can be scheduled for.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
     /// Shutdown the `PubSub` system.
     ///
     /// This function triggers the cancellation token to stop all subscribers.
@@ -37,6 +94,47 @@ impl PubSub {
         self.shutdown.cancel();
     }
 
+    /// Start a background reaper that redelivers messages stuck in `processing`.
+    ///
+    /// Every claim made by this crate sets `status = 'processing'` inside the
+    /// handler's own transaction, so a subscriber that dies mid-callback (panic,
+    /// process kill, lost connection) has that transaction rolled back and its
+    /// message returns to `new` without the reaper. The reaper covers rows that are
+    /// committed as `processing`: it periodically resets any such message whose
+    /// lease has expired — that is, whose `updated_at`
+    /// is older than `visibility_timeout` — back to `new` so it can be picked up
+    /// again. A message whose handler is still running holds a `FOR UPDATE` row lock
+    /// that the reaper skips, so a slow-but-alive handler is never reclaimed no
+    /// matter how long it runs; only orphaned rows are redelivered. Because a
+    /// redelivered message may still have partially run, callbacks must be
+    /// idempotent.
+    ///
+    /// The task stops when the shutdown token is cancelled.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - How often to scan for stuck messages.
+    /// * `visibility_timeout` - How long a message may stay `processing` before it is reclaimed.
+    pub fn start_reaper(
+        &self,
+        interval: Duration,
+        visibility_timeout: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let pool = self.pool.clone();
+        let shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {
+                        let _ = reap_stuck_messages(&pool, visibility_timeout).await;
+                    }
+                }
+            }
+        })
+    }
+
     /// Create a new topic.
     ///
     /// # Arguments
@@ -82,6 +180,68 @@ impl PubSub {
         Ok(())
     }
 
+    /// Publish a message to be delivered at a specific time.
+    ///
+    /// The message is not visible to subscribers until `at`; see [`push`](Self::push)
+    /// for immediate delivery.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to publish the message to.
+    /// * `content` - The content of the message as a byte vector.
+    /// * `at` - The earliest time the message should be delivered.
+    pub async fn push_at(
+        &self,
+        topic: &str,
+        content: &[u8],
+        at: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO messages (topic_id, content, deliver_at)
+            SELECT id, $2, $3
+            FROM topics WHERE name = $1
+            "#,
+            topic,
+            content,
+            at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Publish a message to be delivered after a delay.
+    ///
+    /// Counterpart to [`push_at`](Self::push_at) that schedules delivery relative
+    /// to the database clock (`now() + delay`).
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to publish the message to.
+    /// * `content` - The content of the message as a byte vector.
+    /// * `delay` - How long to wait before the message becomes deliverable.
+    pub async fn push_after(
+        &self,
+        topic: &str,
+        content: &[u8],
+        delay: Duration,
+    ) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO messages (topic_id, content, deliver_at)
+            SELECT id, $2, now() + make_interval(secs => $3)
+            FROM topics WHERE name = $1
+            "#,
+            topic,
+            content,
+            delay.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
     /// Subscribe to a topic.
     ///
     /// # Arguments
@@ -94,6 +254,52 @@ impl PubSub {
     /// * `F` - The type of the callback function.
     /// * `Fut` - The type of the future that the callback returns.
     pub async fn subscribe<F, Fut>(&mut self, topic: &str, callback: F) -> Result<(), Error>
+    where
+        F: FnMut(Vec<u8>) -> Fut + Clone,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        // A dropped connection should not permanently end the subscription. Run the
+        // listen session in a loop, reconnecting with capped exponential backoff on
+        // transient transport errors; all other errors are fatal and are surfaced to
+        // the caller. Each reconnect re-runs the drain query to pick up
+        // anything published while the LISTEN channel was offline.
+        let mut backoff = self.base_delay;
+        loop {
+            if self.shutdown.is_cancelled() {
+                break;
+            }
+            match self.run_listen_session(topic, &callback, &mut backoff).await {
+                // A clean session end means the shutdown token was cancelled.
+                Ok(()) => break,
+                Err(err) if err.is_transient() => {
+                    tokio::select! {
+                        _ = self.shutdown.cancelled() => break,
+                        _ = tokio::time::sleep(backoff) => {}
+                    }
+                    backoff = (backoff * 2).min(self.max_delay);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run a single listen session until shutdown or a connection error.
+    ///
+    /// Connects a fresh `PgListener`, drains any backlog, then processes
+    /// notifications and scheduled deliveries. Returns `Ok(())` on a clean
+    /// shutdown; any surfaced error is classified by the caller of
+    /// [`subscribe`](Self::subscribe) as transient (reconnect) or fatal. On a
+    /// successful connect the caller's `reconnect_backoff` is reset to `base_delay`
+    /// so a drop after a long healthy session recovers fast instead of inheriting a
+    /// ratcheted delay.
+    async fn run_listen_session<F, Fut>(
+        &self,
+        topic: &str,
+        callback: &F,
+        reconnect_backoff: &mut Duration,
+    ) -> Result<(), Error>
     where
         F: FnMut(Vec<u8>) -> Fut + Clone,
         Fut: Future<Output = Result<(), Error>> + Send + 'static,
@@ -101,9 +307,109 @@ impl PubSub {
         // First, listen for new messages from postgres so we don't miss anything
         let mut listener = PgListener::connect_with(&self.pool).await?;
         listener.listen("new_message").await?;
+        *reconnect_backoff = self.base_delay;
         let mut stream = listener.into_stream();
 
-        // Drain the existing messages from the queue
+        // Drain any messages that are already due
+        self.drain_topic(topic, callback).await?;
+
+        loop {
+            // Wake either on a notification, on shutdown, or when the next
+            // scheduled message becomes due, whichever comes first.
+            let delay = self
+                .next_wait(topic)
+                .await?
+                .unwrap_or(Duration::from_secs(3600));
+            let timer = tokio::time::sleep(delay);
+
+            tokio::select! {
+                // If the shutdown token is cancelled, break out of the loop
+                _ = self.shutdown.cancelled() => {
+                    break;
+                }
+                // A scheduled message has become due: drain everything now ready.
+                _ = timer => {
+                    self.drain_topic(topic, callback).await?;
+                }
+                // If the listener receives a notification, process the message
+                notification = stream.next() => {
+                    let notification = match notification {
+                        Some(Ok(notification)) => notification,
+                        // A transport error or stream termination means the
+                        // connection dropped: surface it so the caller reconnects
+                        // and re-drains anything missed while offline.
+                        Some(Err(err)) => return Err(err.into()),
+                        None => return Err(Error::DatabaseError(sqlx::Error::PoolClosed)),
+                    };
+                    {
+                        // A payload that isn't a message id is ignored rather than
+                        // treated as a fatal error that would end the subscription.
+                        if notification.channel() == "new_message" {
+                            if let Ok(message_id) = notification.payload().parse::<i32>() {
+                                let mut tx = self.pool.begin().await?;
+                                // The NOTIFY fires on insert, so a scheduled message
+                                // notifies before it is due and a message already
+                                // handled by the drain is gone: in both cases there is
+                                // no row yet, which is a no-op — the timer or drain
+                                // delivers it later.
+                                let row = sqlx::query!(
+                                    r#"
+                                    SELECT messages.content
+                                    FROM messages, topics
+                                    WHERE
+                                        messages.id = $1 AND
+                                        topics.name = $2 AND
+                                        messages.topic_id = topics.id AND
+                                        messages.status = 'new' AND
+                                        messages.deliver_at <= now() AND
+                                        (messages.next_attempt_at IS NULL OR messages.next_attempt_at <= now())
+                                    LIMIT 1
+                                    "#,
+                                    message_id,
+                                    topic
+                                )
+                                .fetch_optional(&mut *tx)
+                                .await?;
+
+                                if let Some(row) = row {
+                                    sqlx::query!(
+                                        "UPDATE messages SET status = 'processing', updated_at = now() WHERE id = $1",
+                                        message_id
+                                    )
+                                    .execute(&mut *tx)
+                                    .await?;
+
+                                    process_message(
+                                        &mut tx,
+                                        message_id,
+                                        row.content,
+                                        self.max_retries,
+                                        self.base_delay,
+                                        self.max_delay,
+                                        callback.clone(),
+                                    )
+                                    .await?;
+                                    tx.commit().await?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Process every message on `topic` that is currently due.
+    ///
+    /// Shared by the startup drain and the delayed-delivery timer branch of
+    /// [`subscribe`](Self::subscribe).
+    async fn drain_topic<F, Fut>(&self, topic: &str, callback: &F) -> Result<(), Error>
+    where
+        F: FnMut(Vec<u8>) -> Fut + Clone,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
         let id_rows = sqlx::query!(
             r#"
             SELECT messages.id
@@ -111,7 +417,9 @@ impl PubSub {
             WHERE
                 topics.name = $1 AND
                 messages.topic_id = topics.id AND
-                messages.status = 'new'
+                messages.status = 'new' AND
+                messages.deliver_at <= now() AND
+                (messages.next_attempt_at IS NULL OR messages.next_attempt_at <= now())
             ORDER BY messages.published_at ASC
             "#,
             topic
@@ -122,6 +430,8 @@ impl PubSub {
         // Process each message individually
         for row in id_rows {
             let mut tx = self.pool.begin().await?;
+            // Another worker, the reaper, or a second subscriber may already hold the
+            // row; SKIP LOCKED then returns nothing, which is simply not our message.
             let message = sqlx::query!(
                 r#"
                 SELECT messages.content
@@ -131,80 +441,557 @@ impl PubSub {
                 "#,
                 row.id
             )
-            .fetch_one(&mut *tx)
+            .fetch_optional(&mut *tx)
             .await?;
 
-            process_message(&mut tx, row.id, message.content, callback.clone()).await?;
+            let Some(message) = message else {
+                continue;
+            };
+
+            process_message(
+                &mut tx,
+                row.id,
+                message.content,
+                self.max_retries,
+                self.base_delay,
+                self.max_delay,
+                callback.clone(),
+            )
+            .await?;
             tx.commit().await?;
         }
+        Ok(())
+    }
+
+    /// How long until the next pending, not-yet-due message on `topic` becomes ready.
+    ///
+    /// A message is ready once both its `deliver_at` and its retry `next_attempt_at`
+    /// have passed, so its ready time is the greater of the two. The wait is computed
+    /// entirely against the database clock (`… - now()`) so a skewed local clock
+    /// can't floor the delay to zero and busy-spin; it lets the subscribe loop's
+    /// timer wake for both scheduled deliveries and backoff retries without polling.
+    /// Returns `None` when nothing is pending.
+    async fn next_wait(&self, topic: &str) -> Result<Option<Duration>, Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT extract(epoch from (min(greatest(
+                messages.deliver_at,
+                coalesce(messages.next_attempt_at, messages.deliver_at)
+            )) - now())) AS wait
+            FROM messages, topics
+            WHERE
+                topics.name = $1 AND
+                messages.topic_id = topics.id AND
+                messages.status = 'new' AND
+                greatest(
+                    messages.deliver_at,
+                    coalesce(messages.next_attempt_at, messages.deliver_at)
+                ) > now()
+            "#,
+            topic
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row
+            .wait
+            .map(|secs| Duration::from_secs_f64(secs.max(0.0))))
+    }
+
+    /// Subscribe to a topic with a pool of competing consumers.
+    ///
+    /// This behaves like [`subscribe`](Self::subscribe) but spawns `concurrency`
+    /// worker tasks that share a single `PgListener` notification stream. Each
+    /// worker claims messages with `FOR UPDATE SKIP LOCKED`, so no two workers ever
+    /// grab the same row, letting a hot topic scale beyond a single callback's
+    /// latency within one process. Workers drain the backlog on startup and wake on
+    /// each `new_message` notification, on scheduled-delivery timers, and after a
+    /// reconnect, honoring the shutdown token throughout. Like
+    /// [`subscribe`](Self::subscribe) it reconnects the listener with capped
+    /// exponential backoff on transient transport errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to subscribe to.
+    /// * `concurrency` - The number of worker tasks to run.
+    /// * `callback` - The function to call when a message arrives.
+    ///
+    /// # Type Parameters
+    ///
+    /// * `F` - The type of the callback function.
+    /// * `Fut` - The type of the future that the callback returns.
+    pub async fn subscribe_concurrent<F, Fut>(
+        &mut self,
+        topic: &str,
+        concurrency: usize,
+        callback: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Vec<u8>) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        // A single listener fans notifications out to the workers over a broadcast
+        // channel; the actual claiming is done independently by each worker.
+        let (tx, _rx) = tokio::sync::broadcast::channel::<()>(concurrency.max(1));
+
+        let mut handles = Vec::with_capacity(concurrency);
+        for _ in 0..concurrency {
+            let this = self.clone();
+            let topic = topic.to_string();
+            let callback = callback.clone();
+            let mut rx = tx.subscribe();
+            handles.push(tokio::spawn(async move {
+                loop {
+                    // Drain everything currently available to this worker.
+                    loop {
+                        if this.shutdown.is_cancelled() {
+                            return Ok::<(), Error>(());
+                        }
+                        if !this.claim_and_process(&topic, callback.clone()).await? {
+                            break;
+                        }
+                    }
+                    // Nothing left for now: wait for a notification or shutdown.
+                    tokio::select! {
+                        _ = this.shutdown.cancelled() => return Ok(()),
+                        recv = rx.recv() => {
+                            match recv {
+                                // Lagged just means we missed some wakeups; loop and drain anyway.
+                                Ok(()) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                                Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+                            }
+                        }
+                    }
+                }
+            }));
+        }
+
+        // Forward notifications and scheduled-delivery wakeups to the workers,
+        // reconnecting the listener with capped backoff on transient errors.
+        let mut backoff = self.base_delay;
+        let result = loop {
+            if self.shutdown.is_cancelled() {
+                break Ok(());
+            }
+            match self.forward_wakeups(topic, &tx, &mut backoff).await {
+                Ok(()) => break Ok(()),
+                Err(err) if err.is_transient() => {
+                    tokio::select! {
+                        _ = self.shutdown.cancelled() => break Ok(()),
+                        _ = tokio::time::sleep(backoff) => {}
+                    }
+                    backoff = (backoff * 2).min(self.max_delay);
+                }
+                Err(err) => break Err(err),
+            }
+        };
+
+        drop(tx);
+        for handle in handles {
+            handle.await.ok();
+        }
+
+        result
+    }
+
+    /// Run a single listen session for the worker pool, waking workers on each
+    /// notification and scheduled-delivery timer until shutdown or a connection
+    /// error. A fresh wakeup is broadcast on connect so workers drain any backlog
+    /// (including messages published while a previous listener was offline). On a
+    /// successful connect the caller's `reconnect_backoff` is reset to `base_delay`
+    /// so recovery after a long healthy session stays fast.
+    async fn forward_wakeups(
+        &self,
+        topic: &str,
+        tx: &tokio::sync::broadcast::Sender<()>,
+        reconnect_backoff: &mut Duration,
+    ) -> Result<(), Error> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen("new_message").await?;
+        *reconnect_backoff = self.base_delay;
+        let _ = tx.send(());
+        let mut stream = listener.into_stream();
 
         loop {
+            let delay = self
+                .next_wait(topic)
+                .await?
+                .unwrap_or(Duration::from_secs(3600));
+            let timer = tokio::time::sleep(delay);
+
             tokio::select! {
-                // If the shutdown token is cancelled, break out of the loop
-                _ = self.shutdown.cancelled() => {
-                    break;
+                _ = self.shutdown.cancelled() => return Ok(()),
+                // A scheduled message has become due: wake the workers to drain it.
+                _ = timer => {
+                    let _ = tx.send(());
                 }
-                // If the listener receives a notification, process the message
                 notification = stream.next() => {
-                    if let Some(Ok(notification)) = notification {
-                        if notification.channel() == "new_message" {
-                            let message_id: i32 = notification.payload().parse()?;
-                            let mut tx = self.pool.begin().await?;
-                            let row = sqlx::query!(
-                                r#"
-                                SELECT messages.content
-                                FROM messages, topics
-                                WHERE
-                                    messages.id = $1 AND
-                                    topics.name = $2 AND
-                                    messages.topic_id = topics.id AND
-                                    messages.status = 'new'
-                                LIMIT 1
-                                "#,
-                                message_id,
-                                topic
-                            )
-                            .fetch_one(&mut *tx)
-                            .await?;
-
-                            sqlx::query!(
-                                "UPDATE messages SET status = 'processing' WHERE id = $1",
-                                message_id
-                            )
-                            .execute(&mut *tx)
-                            .await?;
-
-                            process_message(&mut tx, message_id, row.content, callback.clone()).await?;
-                            tx.commit().await?;
+                    match notification {
+                        // A closed channel only means every worker has exited.
+                        Some(Ok(n)) if n.channel() == "new_message" => {
+                            let _ = tx.send(());
                         }
+                        Some(Ok(_)) => {}
+                        // Transport errors (and an ended stream) trigger a reconnect.
+                        Some(Err(err)) => return Err(err.into()),
+                        None => return Err(Error::DatabaseError(sqlx::Error::PoolClosed)),
                     }
                 }
             }
         }
+    }
+
+    /// Claim and process a single deliverable message for `topic`.
+    ///
+    /// Returns `Ok(true)` if a message was claimed and handled, or `Ok(false)` if
+    /// none were available. The row is locked with `FOR UPDATE SKIP LOCKED` so
+    /// competing workers never contend for the same message.
+    async fn claim_and_process<F, Fut>(&self, topic: &str, callback: F) -> Result<bool, Error>
+    where
+        F: FnMut(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let mut tx = self.pool.begin().await?;
+        let row = sqlx::query!(
+            r#"
+            SELECT messages.id, messages.content
+            FROM messages, topics
+            WHERE
+                topics.name = $1 AND
+                messages.topic_id = topics.id AND
+                messages.status = 'new' AND
+                messages.deliver_at <= now() AND
+                (messages.next_attempt_at IS NULL OR messages.next_attempt_at <= now())
+            ORDER BY messages.published_at ASC
+            FOR UPDATE OF messages SKIP LOCKED
+            LIMIT 1
+            "#,
+            topic
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(false);
+        };
+
+        sqlx::query!(
+            "UPDATE messages SET status = 'processing', updated_at = now() WHERE id = $1",
+            row.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        process_message(
+            &mut tx,
+            row.id,
+            row.content,
+            self.max_retries,
+            self.base_delay,
+            self.max_delay,
+            callback,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Open a typed, pull-based queue over a topic.
+    ///
+    /// This is the counterpart to the push-based [`subscribe`](Self::subscribe)
+    /// callback: instead of handing messages to a closure, callers pull [`Job`]
+    /// handles themselves and acknowledge completion with [`Job::done`]. Payloads
+    /// are (de)serialized with `serde_json`, so callers work with `T` directly
+    /// rather than raw bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic the queue is bound to.
+    pub fn queue<T>(&self, topic: &str) -> PgQueue<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        PgQueue {
+            pool: self.pool.clone(),
+            topic: topic.to_string(),
+            max_retries: self.max_retries,
+            base_delay: self.base_delay,
+            max_delay: self.max_delay,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A typed, pull-based queue backed by a single topic.
+///
+/// See [`PubSub::queue`] for how to construct one.
+#[derive(Clone)]
+pub struct PgQueue<T> {
+    pool: PgPool,
+    topic: String,
+    max_retries: i32,
+    base_delay: Duration,
+    max_delay: Duration,
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// A pull-based queue of typed payloads.
+///
+/// Mirrors a job-queue `put`/`get_one`/`into_stream` surface: `put` enqueues a
+/// payload, `get_one` leases the next ready payload, and `into_stream` turns the
+/// queue into a [`Stream`] of [`Job`] handles.
+#[allow(async_fn_in_trait)]
+pub trait Queue<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Enqueue a payload and return the id of the created message.
+    async fn put(&self, payload: &T) -> Result<i32, Error>;
+
+    /// Lease the next ready payload, if any, as an owned [`Job`] handle.
+    async fn get_one(&self) -> Result<Option<Job<T>>, Error>;
+
+    /// Consume the queue into a [`Stream`] of [`Job`] handles.
+    ///
+    /// The stream ends once the queue has no ready messages left.
+    fn into_stream(self) -> JobStream<T>;
+}
+
+impl<T> Queue<T> for PgQueue<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    async fn put(&self, payload: &T) -> Result<i32, Error> {
+        let content = serde_json::to_vec(payload)?;
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO messages (topic_id, content)
+            SELECT id, $2
+            FROM topics WHERE name = $1
+            RETURNING id
+            "#,
+            self.topic,
+            content
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.id)
+    }
+
+    async fn get_one(&self) -> Result<Option<Job<T>>, Error> {
+        let mut tx = self.pool.begin().await?;
+        let row = sqlx::query!(
+            r#"
+            SELECT messages.id, messages.content
+            FROM messages, topics
+            WHERE
+                topics.name = $1 AND
+                messages.topic_id = topics.id AND
+                messages.status = 'new' AND
+                messages.deliver_at <= now() AND
+                (messages.next_attempt_at IS NULL OR messages.next_attempt_at <= now())
+            ORDER BY messages.published_at ASC
+            FOR UPDATE OF messages SKIP LOCKED
+            LIMIT 1
+            "#,
+            self.topic
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            "UPDATE messages SET status = 'processing', updated_at = now() WHERE id = $1",
+            row.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let payload = match serde_json::from_slice(&row.content) {
+            Ok(payload) => payload,
+            Err(err) => {
+                // Record an undecodable payload as a failed attempt so it backs off
+                // (and is eventually parked in `failed`) instead of blocking later jobs.
+                reschedule_failed_attempt(
+                    &mut tx,
+                    row.id,
+                    self.max_retries,
+                    self.base_delay,
+                    self.max_delay,
+                )
+                .await?;
+                tx.commit().await?;
+                return Err(err.into());
+            }
+        };
+        Ok(Some(Job {
+            id: row.id,
+            payload,
+            tx: Some(tx),
+            max_retries: self.max_retries,
+            base_delay: self.base_delay,
+            max_delay: self.max_delay,
+        }))
+    }
+
+    fn into_stream(self) -> JobStream<T> {
+        JobStream {
+            queue: self,
+            pending: None,
+        }
+    }
+}
+
+/// A leased queue message with an owned transaction.
+///
+/// The job holds the `FOR UPDATE` lock on its row for as long as it is alive.
+/// Call [`done`](Self::done) to commit completion; if the handle is dropped
+/// without being acknowledged, a cleanup future is spawned that records the
+/// failed attempt with the same backoff/`max_retries` rules as the push path
+/// (see [`PubSub::subscribe`]) — rescheduling the row with `next_attempt_at` or
+/// parking it in `failed` once retries are exhausted.
+///
+/// The cleanup future needs a Tokio runtime to run on. If the handle is dropped
+/// outside a runtime (so [`Handle::try_current`](tokio::runtime::Handle::try_current)
+/// fails) no cleanup future is spawned and the owning transaction is never
+/// committed, so neither the `processing` claim nor a failed attempt is recorded:
+/// the row stays `new` and is offered again once its row lock is released.
+pub struct Job<T> {
+    id: i32,
+    payload: T,
+    tx: Option<sqlx::Transaction<'static, sqlx::Postgres>>,
+    max_retries: i32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
 
+impl<T> Job<T> {
+    /// The id of the underlying message.
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// A reference to the decoded payload.
+    pub fn payload(&self) -> &T {
+        &self.payload
+    }
+
+    /// Acknowledge the job as completed, committing the owning transaction.
+    pub async fn done(mut self) -> Result<(), Error> {
+        if let Some(mut tx) = self.tx.take() {
+            sqlx::query!(
+                "UPDATE messages SET status = 'processed', updated_at = now() WHERE id = $1",
+                self.id
+            )
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+        }
         Ok(())
     }
 }
 
+impl<T> Drop for Job<T> {
+    fn drop(&mut self) {
+        // If the job was acknowledged, `tx` has already been taken and there is
+        // nothing to do. Otherwise spawn an "async drop" that releases the row back
+        // to `new` so the message is retried rather than lost.
+        if let Some(mut tx) = self.tx.take() {
+            let id = self.id;
+            let max_retries = self.max_retries;
+            let base_delay = self.base_delay;
+            let max_delay = self.max_delay;
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                handle.spawn(async move {
+                    let _ = reschedule_failed_attempt(
+                        &mut tx,
+                        id,
+                        max_retries,
+                        base_delay,
+                        max_delay,
+                    )
+                    .await;
+                    let _ = tx.commit().await;
+                });
+            }
+        }
+    }
+}
+
+/// A [`Stream`] of [`Job`] handles pulled from a [`PgQueue`].
+///
+/// Yields `Ok(job)` for each ready message and ends (`None`) once the queue is
+/// drained. Transient database errors and undeserializable payloads are surfaced
+/// as `Err` items rather than swallowed as a clean end, so a consumer can tell
+/// "queue empty" apart from "lookup failed" and one bad row doesn't silently kill
+/// delivery of every later job.
+pub struct JobStream<T> {
+    queue: PgQueue<T>,
+    #[allow(clippy::type_complexity)]
+    pending: Option<Pin<Box<dyn Future<Output = Result<Option<Job<T>>, Error>> + Send>>>,
+}
+
+impl<T> Stream for JobStream<T>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    type Item = Result<Job<T>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.pending.is_none() {
+            let queue = this.queue.clone();
+            this.pending = Some(Box::pin(async move { queue.get_one().await }));
+        }
+        let fut = this.pending.as_mut().unwrap();
+        match fut.as_mut().poll(cx) {
+            Poll::Ready(result) => {
+                this.pending = None;
+                match result {
+                    Ok(Some(job)) => Poll::Ready(Some(Ok(job))),
+                    // No more ready messages: the queue is drained.
+                    Ok(None) => Poll::Ready(None),
+                    // Surface errors instead of masquerading as end-of-stream.
+                    Err(err) => Poll::Ready(Some(Err(err))),
+                }
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
 impl Drop for PubSub {
     fn drop(&mut self) {
         self.shutdown.cancel();
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn process_message<'a, F, Fut>(
     tx: &'a mut sqlx::Transaction<'static, sqlx::Postgres>,
     message_id: i32,
     message_content: Vec<u8>,
+    max_retries: i32,
+    base_delay: Duration,
+    max_delay: Duration,
     mut callback: F,
 ) -> Result<(), Error>
 where
     F: FnMut(Vec<u8>) -> Fut,
     Fut: Future<Output = Result<(), Error>> + Send + 'static,
 {
-    callback(message_content).await?;
+    // A callback error must not abort the subscribe loop: instead we record the
+    // failed attempt and either reschedule the message for a later retry or, once
+    // `max_retries` is exceeded, park it in the `failed` state.
+    if callback(message_content).await.is_err() {
+        reschedule_failed_attempt(tx, message_id, max_retries, base_delay, max_delay).await?;
+        return Ok(());
+    }
+
     sqlx::query!(
-        "UPDATE messages SET status = 'processed' WHERE id = $1",
+        "UPDATE messages SET status = 'processed', updated_at = now() WHERE id = $1",
         message_id
     )
     .execute(&mut **tx)
@@ -212,6 +999,86 @@ where
     Ok(())
 }
 
+/// Record a failed delivery attempt against a message.
+///
+/// Increments `attempts` and either reschedules the message back to `new` with an
+/// exponential-backoff `next_attempt_at`, or — once `max_retries` is exceeded —
+/// parks it in `failed`. Shared by the push-based [`process_message`] and the
+/// pull-based [`Job`] drop path so both honor the same retry semantics.
+async fn reschedule_failed_attempt(
+    tx: &mut sqlx::Transaction<'static, sqlx::Postgres>,
+    message_id: i32,
+    max_retries: i32,
+    base_delay: Duration,
+    max_delay: Duration,
+) -> Result<(), Error> {
+    let attempts = sqlx::query!(
+        "UPDATE messages SET attempts = attempts + 1 WHERE id = $1 RETURNING attempts",
+        message_id
+    )
+    .fetch_one(&mut **tx)
+    .await?
+    .attempts;
+
+    if attempts > max_retries {
+        sqlx::query!(
+            "UPDATE messages SET status = 'failed', updated_at = now() WHERE id = $1",
+            message_id
+        )
+        .execute(&mut **tx)
+        .await?;
+    } else {
+        let delay = backoff(base_delay, max_delay, attempts).as_secs_f64();
+        sqlx::query!(
+            r#"
+            UPDATE messages
+            SET status = 'new', updated_at = now(), next_attempt_at = now() + make_interval(secs => $2)
+            WHERE id = $1
+            "#,
+            message_id,
+            delay
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Reclaim messages whose `processing` lease has expired.
+///
+/// A live handler holds a `FOR UPDATE` row lock on its message for the duration
+/// of its callback — that lock *is* the lease. The reaper selects candidate rows
+/// with `FOR UPDATE SKIP LOCKED`, so a message still being processed is skipped
+/// and never prematurely reclaimed, regardless of how long the handler runs. Only
+/// rows that are both stale (`updated_at` older than `visibility_timeout`) and
+/// unlocked are reset to `new`.
+async fn reap_stuck_messages(pool: &PgPool, visibility_timeout: Duration) -> Result<(), Error> {
+    sqlx::query!(
+        r#"
+        UPDATE messages
+        SET status = 'new', updated_at = now()
+        WHERE id IN (
+            SELECT id
+            FROM messages
+            WHERE status = 'processing' AND updated_at < now() - make_interval(secs => $1)
+            FOR UPDATE SKIP LOCKED
+        )
+        "#,
+        visibility_timeout.as_secs_f64()
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Compute the exponential backoff delay for a given attempt.
+///
+/// The delay doubles with each attempt (`base * 2^attempts`) and is capped at `max`.
+fn backoff(base: Duration, max: Duration, attempts: i32) -> Duration {
+    let secs = base.as_secs_f64() * 2f64.powi(attempts.max(0));
+    Duration::from_secs_f64(secs.min(max.as_secs_f64()))
+}
+
 /// Errors that can occur in the Pub/Sub system.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -223,6 +1090,27 @@ pub enum Error {
     EnvVarError(#[from] std::env::VarError),
     #[error("parse int error: {0}")]
     ParseIntError(#[from] std::num::ParseIntError),
+    #[error("serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+impl Error {
+    /// Whether this error is a transient transport failure worth retrying.
+    ///
+    /// Connection-level database errors (dropped sockets, closed or timed-out
+    /// pools, TLS failures) are transient; parse errors, missing topics
+    /// (`RowNotFound`) and everything else are fatal.
+    fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::DatabaseError(
+                sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+            )
+        )
+    }
 }
 
 /// Run database migrations.
@@ -7,12 +7,13 @@
 
 use sqlx::PgPool;
 use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 
-use unisub::PubSub;
+use unisub::{Error, PubSub};
 
 #[tokio::test]
 async fn test_pub_sub_flow() {
@@ -155,3 +156,103 @@ async fn test_message_order() {
     let received_messages = received_messages.lock().await;
     assert_eq!(*received_messages, vec![b"1".to_vec(), b"2".to_vec()]);
 }
+
+#[tokio::test]
+async fn test_scheduled_delivery() {
+    let database_url = env::var("DATABASE_URL").unwrap();
+    let pool = PgPool::connect(&database_url).await.unwrap();
+    let pubsub = PubSub::new(pool).await.unwrap();
+
+    pubsub.create_topic("scheduled_topic").await.ok();
+
+    let received_messages = Arc::new(Mutex::new(Vec::new()));
+
+    let mut pubsub2 = pubsub.clone();
+    let received_messages2 = received_messages.clone();
+    let handle = tokio::spawn(async move {
+        pubsub2
+            .subscribe("scheduled_topic", move |message| {
+                let received_messages = received_messages2.clone();
+                let message = message.clone();
+                async move {
+                    received_messages.lock().await.push(message);
+                    Ok(())
+                }
+            })
+            .await
+            .expect("Failed to subscribe to topic");
+    });
+
+    // Schedule a message for the near future. The insert fires NOTIFY immediately,
+    // which must not crash the subscriber even though the message isn't due yet.
+    pubsub
+        .push_after("scheduled_topic", b"later", Duration::from_secs(2))
+        .await
+        .expect("Failed to schedule message");
+
+    // It should not be delivered before it is due.
+    sleep(Duration::from_secs(1)).await;
+    assert!(
+        received_messages.lock().await.is_empty(),
+        "Scheduled message delivered early"
+    );
+
+    // ...but it should arrive once the deliver_at timer fires.
+    sleep(Duration::from_secs(2)).await;
+    assert_eq!(*received_messages.lock().await, vec![b"later".to_vec()]);
+
+    pubsub.shutdown().await;
+    handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_retry_reschedule() {
+    let database_url = env::var("DATABASE_URL").unwrap();
+    let pool = PgPool::connect(&database_url).await.unwrap();
+    let pubsub = PubSub::new(pool)
+        .await
+        .unwrap()
+        .base_delay(Duration::from_millis(200))
+        .max_delay(Duration::from_secs(1));
+
+    pubsub.create_topic("retry_topic").await.ok();
+
+    // Count deliveries so we can assert the message was retried after failing once.
+    let attempts = Arc::new(AtomicUsize::new(0));
+
+    let mut pubsub2 = pubsub.clone();
+    let attempts2 = attempts.clone();
+    let handle = tokio::spawn(async move {
+        pubsub2
+            .subscribe("retry_topic", move |_message| {
+                let attempts = attempts2.clone();
+                async move {
+                    // Fail the first delivery, succeed on the backoff retry.
+                    if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                        Err(Error::EnvVarError(env::VarError::NotPresent))
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .await
+            .expect("Failed to subscribe to topic");
+    });
+
+    pubsub
+        .push("retry_topic", b"retry me")
+        .await
+        .expect("Failed to push message");
+
+    // Allow time for the initial failure plus the ~400ms backoff retry (twice the
+    // 200ms base after one failed attempt).
+    sleep(Duration::from_secs(2)).await;
+
+    pubsub.shutdown().await;
+    handle.await.unwrap();
+
+    assert!(
+        attempts.load(Ordering::SeqCst) >= 2,
+        "Message was not retried after a failed callback"
+    );
+}